@@ -1,10 +1,19 @@
-use crate::server::data::{ActiveMock, MockDefinition, MockIdentification, MockServerState};
+use crate::data::HttpMockRequest;
+use crate::server::data::{
+    ActiveMock, ClosestMatchReport, MockDefinition, MockIdentification, MockServerState,
+    VerificationReport,
+};
+use async_trait::async_trait;
 use hyper::body::Bytes;
-use hyper::{Body, Error, Method as HyperMethod, Request, StatusCode};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Error, Method as HyperMethod, Request, StatusCode};
 use std::cell::RefCell;
 use std::fmt::Debug;
+use std::future::Future;
 use std::sync::Arc;
-use crate::server::handlers::{add_new_mock, read_one, delete_one, delete_all};
+use crate::server::handlers::{
+    add_new_mock, read_one, delete_one, delete_all, verify_one, find_closest_match,
+};
 
 thread_local!(
     static TOKIO_RUNTIME: RefCell<tokio::runtime::Runtime> = {
@@ -33,14 +42,58 @@ pub enum Method {
     PATCH,
 }
 
+/// Drives the given future on the thread-local runtime. Used by the sync wrapper methods on
+/// [MockServerAdapter] so callers outside of an async context can still block on the result.
+fn block_on_local<F: Future>(future: F) -> F::Output {
+    TOKIO_RUNTIME.with(|runtime| {
+        let local = tokio::task::LocalSet::new();
+        let mut rt = &mut *runtime.borrow_mut();
+        local.block_on(&mut rt, future)
+    })
+}
+
+/// Both a sync and an async API to drive the mock server. The sync methods are thin wrappers
+/// around the async ones (they block on the caller's thread-local runtime), so code running
+/// inside an existing async executor should prefer the `_async` variants to avoid spinning up a
+/// nested runtime.
+#[async_trait]
 pub(crate) trait MockServerAdapter {
     fn server_port(&self) -> u16;
     fn server_host(&self) -> String;
     fn server_address(&self) -> String;
-    fn create_mock(&self, mock: &MockDefinition) -> Result<MockIdentification, String>;
-    fn fetch_mock(&self, mock_id: usize) -> Result<ActiveMock, String>;
-    fn delete_mock(&self, mock_id: usize) -> Result<(), String>;
-    fn delete_all_mocks(&self) -> Result<(), String>;
+
+    fn create_mock(&self, mock: &MockDefinition) -> Result<MockIdentification, String> {
+        block_on_local(self.create_mock_async(mock))
+    }
+    async fn create_mock_async(&self, mock: &MockDefinition) -> Result<MockIdentification, String>;
+
+    fn fetch_mock(&self, mock_id: usize) -> Result<ActiveMock, String> {
+        block_on_local(self.fetch_mock_async(mock_id))
+    }
+    async fn fetch_mock_async(&self, mock_id: usize) -> Result<ActiveMock, String>;
+
+    fn delete_mock(&self, mock_id: usize) -> Result<(), String> {
+        block_on_local(self.delete_mock_async(mock_id))
+    }
+    async fn delete_mock_async(&self, mock_id: usize) -> Result<(), String>;
+
+    fn delete_all_mocks(&self) -> Result<(), String> {
+        block_on_local(self.delete_all_mocks_async())
+    }
+    async fn delete_all_mocks_async(&self) -> Result<(), String>;
+
+    fn verify_mock(&self, mock_id: usize) -> Result<VerificationReport, String> {
+        block_on_local(self.verify_mock_async(mock_id))
+    }
+    async fn verify_mock_async(&self, mock_id: usize) -> Result<VerificationReport, String>;
+
+    fn find_closest_match(&self, req: &HttpMockRequest) -> Result<Option<ClosestMatchReport>, String> {
+        block_on_local(self.find_closest_match_async(req))
+    }
+    async fn find_closest_match_async(
+        &self,
+        req: &HttpMockRequest,
+    ) -> Result<Option<ClosestMatchReport>, String>;
 }
 
 /// This adapter allows to access the servers management functionality.
@@ -52,14 +105,16 @@ pub(crate) trait MockServerAdapter {
 pub struct RemoteMockServerAdapter {
     pub(crate) host: String,
     pub(crate) port: u16,
+    client: Client<HttpConnector>,
 }
 
 impl RemoteMockServerAdapter {
     pub(crate) fn new(host: String, port: u16) -> RemoteMockServerAdapter {
-        RemoteMockServerAdapter { host, port }
+        RemoteMockServerAdapter { host, port, client: Client::new() }
     }
 }
 
+#[async_trait]
 impl MockServerAdapter for RemoteMockServerAdapter {
     fn server_port(&self) -> u16 {
         self.port
@@ -73,7 +128,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
         format!("{}:{}", self.server_host(), self.server_port())
     }
 
-    fn create_mock(&self, mock: &MockDefinition) -> Result<MockIdentification, String> {
+    async fn create_mock_async(&self, mock: &MockDefinition) -> Result<MockIdentification, String> {
         // Serialize to JSON
         let json = serde_json::to_string(mock);
         if let Err(err) = json {
@@ -91,7 +146,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
             .body(Body::from(json))
             .expect("Cannot build request");
 
-        let response = execute_request(request);
+        let response = execute_request_async(&self.client, request).await;
         if let Err(err) = response {
             return Err(format!("cannot send request to mock server: {}", err));
         }
@@ -115,7 +170,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
         return Ok(response.unwrap());
     }
 
-    fn fetch_mock(&self, mock_id: usize) -> Result<ActiveMock, String> {
+    async fn fetch_mock_async(&self, mock_id: usize) -> Result<ActiveMock, String> {
         // Send the request to the mock server
         let request_url = format!("http://{}/__mocks/{}", &self.server_address(), mock_id);
         let request = Request::builder()
@@ -124,7 +179,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
             .body(Body::empty())
             .expect("Cannot build request");
 
-        let response = execute_request(request);
+        let response = execute_request_async(&self.client, request).await;
         if let Err(err) = response {
             return Err(format!("cannot send request to mock server: {}", err));
         }
@@ -148,7 +203,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
         return Ok(response.unwrap());
     }
 
-    fn delete_mock(&self, mock_id: usize) -> Result<(), String> {
+    async fn delete_mock_async(&self, mock_id: usize) -> Result<(), String> {
         // Send the request to the mock server
         let request_url = format!("http://{}/__mocks/{}", &self.server_address(), mock_id);
         let request = Request::builder()
@@ -157,7 +212,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
             .body(Body::empty())
             .expect("Cannot build request");
 
-        let response = execute_request(request);
+        let response = execute_request_async(&self.client, request).await;
         if let Err(err) = response {
             return Err(format!("cannot send request to mock server: {}", err));
         }
@@ -174,7 +229,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
         return Ok(());
     }
 
-    fn delete_all_mocks(&self) -> Result<(), String> {
+    async fn delete_all_mocks_async(&self) -> Result<(), String> {
         // Send the request to the mock server
         let request_url = format!("http://{}/__mocks", &self.server_address());
         let request = Request::builder()
@@ -183,7 +238,7 @@ impl MockServerAdapter for RemoteMockServerAdapter {
             .body(Body::empty())
             .expect("Cannot build request");
 
-        let response = execute_request(request);
+        let response = execute_request_async(&self.client, request).await;
         if let Err(err) = response {
             return Err(format!("cannot send request to mock server: {}", err));
         }
@@ -200,6 +255,88 @@ impl MockServerAdapter for RemoteMockServerAdapter {
 
         return Ok(());
     }
+
+    async fn verify_mock_async(&self, mock_id: usize) -> Result<VerificationReport, String> {
+        // Send the request to the mock server
+        let request_url = format!("http://{}/__mocks/{}/verify", &self.server_address(), mock_id);
+        let request = Request::builder()
+            .method(HyperMethod::GET)
+            .uri(request_url)
+            .body(Body::empty())
+            .expect("Cannot build request");
+
+        let response = execute_request_async(&self.client, request).await;
+        if let Err(err) = response {
+            return Err(format!("cannot send request to mock server: {}", err));
+        }
+
+        let (status, body) = response.unwrap();
+
+        // Evaluate response status code
+        if status != 200 {
+            return Err(format!(
+                "could not verify mock. Mock server response: status = {}, message = {}",
+                status, body
+            ));
+        }
+
+        // Create response object
+        let response: serde_json::Result<VerificationReport> = serde_json::from_str(&body);
+        if let Err(err) = response {
+            return Err(format!("cannot deserialize mock server response: {}", err));
+        }
+
+        return Ok(response.unwrap());
+    }
+
+    async fn find_closest_match_async(
+        &self,
+        req: &HttpMockRequest,
+    ) -> Result<Option<ClosestMatchReport>, String> {
+        // Serialize to JSON
+        let json = serde_json::to_string(req);
+        if let Err(err) = json {
+            return Err(format!("cannot serialize request to JSON: {}", err));
+        }
+        let json = json.unwrap();
+
+        // Send the request to the mock server
+        let request_url = format!("http://{}/__diagnostics/closest-match", &self.server_address());
+
+        let request = Request::builder()
+            .method(HyperMethod::POST)
+            .uri(request_url)
+            .header("Content-Type", "application/json")
+            .body(Body::from(json))
+            .expect("Cannot build request");
+
+        let response = execute_request_async(&self.client, request).await;
+        if let Err(err) = response {
+            return Err(format!("cannot send request to mock server: {}", err));
+        }
+
+        let (status, body) = response.unwrap();
+
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        // Evaluate the response status
+        if status != 200 {
+            return Err(format!(
+                "could not fetch closest match. Mock server response: status = {}, message = {}",
+                status, body
+            ));
+        }
+
+        // Create response object
+        let response: serde_json::Result<ClosestMatchReport> = serde_json::from_str(&body);
+        if let Err(err) = response {
+            return Err(format!("cannot deserialize mock server response: {}", err));
+        }
+
+        return Ok(Some(response.unwrap()));
+    }
 }
 
 pub struct LocalMockServerAdapter {
@@ -214,6 +351,7 @@ impl LocalMockServerAdapter {
     }
 }
 
+#[async_trait]
 impl MockServerAdapter for LocalMockServerAdapter {
     fn server_port(&self) -> u16 {
         self.port
@@ -227,19 +365,19 @@ impl MockServerAdapter for LocalMockServerAdapter {
         format!("{}:{}", self.server_host(), self.server_port())
     }
 
-    fn create_mock(&self, mock: &MockDefinition) -> Result<MockIdentification, String> {
+    async fn create_mock_async(&self, mock: &MockDefinition) -> Result<MockIdentification, String> {
         let id = add_new_mock(&self.local_state, mock.clone())?;
         return Ok(MockIdentification::new(id));
     }
 
-    fn fetch_mock(&self, mock_id: usize) -> Result<ActiveMock, String> {
+    async fn fetch_mock_async(&self, mock_id: usize) -> Result<ActiveMock, String> {
         return match read_one(&self.local_state, mock_id)? {
             Some(mock) => Ok(mock),
             None => Err("Cannot find mock".to_string())
         };
     }
 
-    fn delete_mock(&self, mock_id: usize) -> Result<(), String> {
+    async fn delete_mock_async(&self, mock_id: usize) -> Result<(), String> {
         let deleted = delete_one(&self.local_state, mock_id)?;
         return match deleted {
             false => Err("Mock could not deleted".to_string()),
@@ -247,10 +385,24 @@ impl MockServerAdapter for LocalMockServerAdapter {
         };
     }
 
-    fn delete_all_mocks(&self) -> Result<(), String> {
+    async fn delete_all_mocks_async(&self) -> Result<(), String> {
         delete_all(&self.local_state)?;
         return Ok(());
     }
+
+    async fn verify_mock_async(&self, mock_id: usize) -> Result<VerificationReport, String> {
+        return match verify_one(&self.local_state, mock_id)? {
+            Some(report) => Ok(report),
+            None => Err("Cannot find mock".to_string())
+        };
+    }
+
+    async fn find_closest_match_async(
+        &self,
+        req: &HttpMockRequest,
+    ) -> Result<Option<ClosestMatchReport>, String> {
+        return find_closest_match(&self.local_state, req);
+    }
 }
 
 /// Enables enum to_string conversion
@@ -260,22 +412,87 @@ impl std::fmt::Display for Method {
     }
 }
 
-/// Executes an HTTP request synchronously
-fn execute_request(req: Request<Body>) -> Result<(StatusCode, String), Error> {
-    return TOKIO_RUNTIME.with(|runtime| {
-        let local = tokio::task::LocalSet::new();
-        let mut rt = &mut *runtime.borrow_mut();
-        return local.block_on(&mut rt, async {
-            let client = hyper::Client::new();
+/// Executes an HTTP request against the mock server using a pooled, reused client.
+async fn execute_request_async(
+    client: &Client<HttpConnector>,
+    req: Request<Body>,
+) -> Result<(StatusCode, String), Error> {
+    let resp = client.request(req).await?;
+    let status = resp.status();
+
+    let body: Bytes = hyper::body::to_bytes(resp.into_body()).await?;
+    let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+    Ok((status, body_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::RequestRequirements;
+    use crate::server::data::MockServerHttpResponse;
+
+    fn adapter() -> LocalMockServerAdapter {
+        LocalMockServerAdapter::new("localhost".to_string(), 0, Arc::new(MockServerState::new()))
+    }
+
+    fn mock_definition() -> MockDefinition {
+        MockDefinition {
+            request: RequestRequirements::default(),
+            response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+            expected_at_least: None,
+            expected_at_most: None,
+        }
+    }
 
-            let resp = client.request(req).await.unwrap();
-            let status = resp.status();
+    #[test]
+    fn create_fetch_and_delete_mock_delegate_to_the_async_variants() {
+        let adapter = adapter();
 
-            let body: Bytes = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let id = adapter.create_mock(&mock_definition()).unwrap();
+        let fetched = adapter.fetch_mock(id.id).unwrap();
+        assert_eq!(id.id, fetched.id);
 
-            let body_str = String::from_utf8(body.to_vec()).unwrap();
+        adapter.delete_mock(id.id).unwrap();
+        assert!(adapter.fetch_mock(id.id).is_err());
+    }
+
+    #[test]
+    fn verify_mock_delegates_to_the_async_variant() {
+        let adapter = adapter();
+        let id = adapter.create_mock(&mock_definition()).unwrap();
+
+        let report = adapter.verify_mock(id.id).unwrap();
+        assert_eq!(0, report.hits);
+        assert!(report.satisfied);
+    }
 
-            Ok((status, body_str))
-        });
-    });
+    #[test]
+    fn delete_all_mocks_delegates_to_the_async_variant() {
+        let adapter = adapter();
+        adapter.create_mock(&mock_definition()).unwrap();
+        adapter.create_mock(&mock_definition()).unwrap();
+
+        adapter.delete_all_mocks().unwrap();
+
+        assert!(adapter.verify_mock(0).is_err());
+        assert!(adapter.verify_mock(1).is_err());
+    }
+
+    #[test]
+    fn find_closest_match_delegates_to_the_async_variant() {
+        let adapter = adapter();
+        let id = adapter.create_mock(&mock_definition()).unwrap();
+
+        let req = HttpMockRequest {
+            path: "/".to_string(),
+            method: "GET".to_string(),
+            headers: None,
+            query_params: None,
+            body: None,
+        };
+        let report = adapter.find_closest_match(&req).unwrap().unwrap();
+
+        assert_eq!(id.id, report.mock_id);
+    }
 }
@@ -0,0 +1,137 @@
+use crate::data::HttpMockRequest;
+use crate::server::data::{MockDefinition, MockServerState};
+use crate::server::handlers::add_new_mock;
+use crate::server::matchers::{ClosureMatcher, MismatchDescription};
+use std::sync::Arc;
+
+/// Builds and registers a mock against a local (in-process) mock server.
+pub struct MockBuilder {
+    state: Arc<MockServerState>,
+    definition: MockDefinition,
+    custom_matchers: Vec<ClosureMatcher>,
+}
+
+impl MockBuilder {
+    pub(crate) fn new(state: Arc<MockServerState>, definition: MockDefinition) -> Self {
+        MockBuilder { state, definition, custom_matchers: Vec::new() }
+    }
+
+    /// Registers a predicate the incoming request must satisfy, for checks the built-in
+    /// comparators can't express (e.g. a signature header or a body checksum). Only takes effect
+    /// when the mock is registered against a local mock server: a `RemoteMockServerAdapter` has
+    /// no way to serialize the closure to the remote process, so remote mocks ignore it.
+    pub fn match_custom<M>(self, matcher: M) -> Self
+    where
+        M: Fn(&HttpMockRequest) -> bool + Send + Sync + 'static,
+    {
+        self.push_custom_matcher(matcher, None)
+    }
+
+    /// Like [`match_custom`](Self::match_custom), but also takes a closure that describes why the
+    /// request was rejected, for inclusion in a `find_closest_match` diagnostic.
+    pub fn match_custom_with_description<M, D>(self, matcher: M, describe: D) -> Self
+    where
+        M: Fn(&HttpMockRequest) -> bool + Send + Sync + 'static,
+        D: Fn(&HttpMockRequest) -> String + Send + Sync + 'static,
+    {
+        self.push_custom_matcher(matcher, Some(Box::new(describe)))
+    }
+
+    fn push_custom_matcher<M>(mut self, matcher: M, mismatch_description: Option<MismatchDescription>) -> Self
+    where
+        M: Fn(&HttpMockRequest) -> bool + Send + Sync + 'static,
+    {
+        self.custom_matchers.push(ClosureMatcher { matcher: Box::new(matcher), mismatch_description });
+        self
+    }
+
+    /// Registers the mock and returns its id.
+    pub fn create(self) -> Result<usize, String> {
+        let id = add_new_mock(&self.state, self.definition)?;
+
+        if !self.custom_matchers.is_empty() {
+            state_custom_matchers(&self.state, id, self.custom_matchers)?;
+        }
+
+        Ok(id)
+    }
+}
+
+fn state_custom_matchers(
+    state: &MockServerState,
+    id: usize,
+    matchers: Vec<ClosureMatcher>,
+) -> Result<(), String> {
+    state.custom_matchers.lock().map_err(|err| err.to_string())?.insert(id, matchers);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::RequestRequirements;
+    use crate::server::data::MockServerHttpResponse;
+    use crate::server::handlers::{find_closest_match, find_match};
+
+    #[test]
+    fn match_custom_registers_a_closure_matcher_against_the_mock() {
+        let state = Arc::new(MockServerState::new());
+        let definition = MockDefinition {
+            request: RequestRequirements::default(),
+            response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+            expected_at_least: None,
+            expected_at_most: None,
+        };
+
+        let id = MockBuilder::new(state.clone(), definition)
+            .match_custom(|req: &HttpMockRequest| req.path == "/allowed")
+            .create()
+            .unwrap();
+
+        let rejected = HttpMockRequest {
+            path: "/denied".to_string(),
+            method: "GET".to_string(),
+            headers: None,
+            query_params: None,
+            body: None,
+        };
+        assert_eq!(None, find_match(&state, &rejected).unwrap());
+
+        let accepted = HttpMockRequest { path: "/allowed".to_string(), ..rejected };
+        assert_eq!(Some(id), find_match(&state, &accepted).unwrap());
+    }
+
+    #[test]
+    fn match_custom_with_description_is_reachable_from_the_builder() {
+        let state = Arc::new(MockServerState::new());
+        let definition = MockDefinition {
+            request: RequestRequirements::default(),
+            response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+            expected_at_least: None,
+            expected_at_most: None,
+        };
+
+        let id = MockBuilder::new(state.clone(), definition)
+            .match_custom_with_description(
+                |req: &HttpMockRequest| req.path == "/allowed",
+                |req: &HttpMockRequest| format!("unexpected path {}", req.path),
+            )
+            .create()
+            .unwrap();
+
+        let rejected = HttpMockRequest {
+            path: "/denied".to_string(),
+            method: "GET".to_string(),
+            headers: None,
+            query_params: None,
+            body: None,
+        };
+        assert_eq!(None, find_match(&state, &rejected).unwrap());
+
+        let report = find_closest_match(&state, &rejected).unwrap().unwrap();
+        assert!(report.diff.contains("unexpected path /denied"));
+
+        let accepted = HttpMockRequest { path: "/allowed".to_string(), ..rejected };
+        assert_eq!(Some(id), find_match(&state, &accepted).unwrap());
+    }
+}
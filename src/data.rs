@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// An HTTP request as it was actually received by the mock server.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HttpMockRequest {
+    pub path: String,
+    pub method: String,
+    pub headers: Option<Vec<(String, String)>>,
+    pub query_params: Option<Vec<(String, String)>>,
+    pub body: Option<String>,
+}
+
+/// The conditions an incoming request must satisfy for a mock to match it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RequestRequirements {
+    pub path: Option<String>,
+    pub method: Option<String>,
+    pub headers: Option<Vec<(String, String)>>,
+    pub query_params: Option<Vec<(String, String)>>,
+    pub body: Option<String>,
+}
@@ -0,0 +1,3 @@
+pub mod api;
+pub(crate) mod data;
+pub(crate) mod server;
@@ -0,0 +1,88 @@
+use crate::data::RequestRequirements;
+use crate::server::matchers::{ClosureMatcher, Mismatch};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// The canned response a mock sends back once its request requirements are satisfied.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MockServerHttpResponse {
+    pub status: Option<u16>,
+    pub headers: Option<Vec<(String, String)>>,
+    pub body: Option<String>,
+}
+
+/// Everything needed to register a mock: what request it should react to, what it responds
+/// with, and (optionally) how many times it is expected to be called.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MockDefinition {
+    pub request: RequestRequirements,
+    pub response: MockServerHttpResponse,
+    pub expected_at_least: Option<usize>,
+    pub expected_at_most: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MockIdentification {
+    pub id: usize,
+}
+
+impl MockIdentification {
+    pub fn new(id: usize) -> Self {
+        MockIdentification { id }
+    }
+}
+
+/// A mock as it is tracked by the server: its definition, plus how many times it has matched an
+/// incoming request.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActiveMock {
+    pub id: usize,
+    pub definition: MockDefinition,
+    pub hits: usize,
+}
+
+/// The result of verifying that a mock was called within its configured hit range.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VerificationReport {
+    pub hits: usize,
+    pub expected_at_least: Option<usize>,
+    pub expected_at_most: Option<usize>,
+    pub satisfied: bool,
+}
+
+/// Diagnostics returned when an incoming request matched zero registered mocks: the mock that
+/// came closest (lowest aggregate mismatch score), its mismatches ranked by score (largest first),
+/// and a rendered diff.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClosestMatchReport {
+    pub mock_id: usize,
+    pub mismatches: Vec<Mismatch>,
+    pub diff: String,
+}
+
+/// In-memory state of a mock server: all mocks registered against it, keyed by id, plus any
+/// closure-backed custom matchers registered against those mocks. Custom matchers live here
+/// (rather than on `MockDefinition`) because closures can't be serialized, so they only ever
+/// exist for a `LocalMockServerAdapter`.
+pub struct MockServerState {
+    pub(crate) mocks: Mutex<BTreeMap<usize, ActiveMock>>,
+    pub(crate) next_id: Mutex<usize>,
+    pub(crate) custom_matchers: Mutex<BTreeMap<usize, Vec<ClosureMatcher>>>,
+}
+
+impl MockServerState {
+    pub fn new() -> Self {
+        MockServerState {
+            mocks: Mutex::new(BTreeMap::new()),
+            next_id: Mutex::new(0),
+            custom_matchers: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl Default for MockServerState {
+    fn default() -> Self {
+        MockServerState::new()
+    }
+}
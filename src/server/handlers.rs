@@ -0,0 +1,492 @@
+use crate::data::{HttpMockRequest, RequestRequirements};
+use crate::server::data::{
+    ActiveMock, ClosestMatchReport, MockDefinition, MockServerState, VerificationReport,
+};
+use crate::server::matchers::{diff_str, distance_for, Matcher, Mismatch, SimpleDiffResult, Tokenizer};
+
+pub(crate) fn add_new_mock(state: &MockServerState, definition: MockDefinition) -> Result<usize, String> {
+    let mut next_id = state.next_id.lock().map_err(|err| err.to_string())?;
+    let id = *next_id;
+    *next_id += 1;
+
+    let mock = ActiveMock { id, definition, hits: 0 };
+    state.mocks.lock().map_err(|err| err.to_string())?.insert(id, mock);
+
+    Ok(id)
+}
+
+pub(crate) fn read_one(state: &MockServerState, id: usize) -> Result<Option<ActiveMock>, String> {
+    Ok(state.mocks.lock().map_err(|err| err.to_string())?.get(&id).cloned())
+}
+
+pub(crate) fn delete_one(state: &MockServerState, id: usize) -> Result<bool, String> {
+    let deleted = state.mocks.lock().map_err(|err| err.to_string())?.remove(&id).is_some();
+    state.custom_matchers.lock().map_err(|err| err.to_string())?.remove(&id);
+    Ok(deleted)
+}
+
+pub(crate) fn delete_all(state: &MockServerState) -> Result<(), String> {
+    state.mocks.lock().map_err(|err| err.to_string())?.clear();
+    state.custom_matchers.lock().map_err(|err| err.to_string())?.clear();
+    Ok(())
+}
+
+/// Finds the first registered mock whose requirements — including any custom matchers registered
+/// against it — are satisfied by `req`, and records a hit against it. Returns the matched mock's
+/// id, if any.
+pub(crate) fn find_match(state: &MockServerState, req: &HttpMockRequest) -> Result<Option<usize>, String> {
+    let mut mocks = state.mocks.lock().map_err(|err| err.to_string())?;
+    let custom_matchers = state.custom_matchers.lock().map_err(|err| err.to_string())?;
+
+    let matched_id = mocks
+        .values()
+        .find(|mock| {
+            request_satisfies(req, &mock.definition.request)
+                && custom_matchers.get(&mock.id).is_none_or(|matchers| {
+                    matchers.iter().all(|matcher| matcher.matches(req, &mock.definition.request))
+                })
+        })
+        .map(|mock| mock.id);
+
+    if let Some(id) = matched_id {
+        if let Some(mock) = mocks.get_mut(&id) {
+            mock.hits += 1;
+        }
+    }
+
+    Ok(matched_id)
+}
+
+pub(crate) fn verify_one(state: &MockServerState, id: usize) -> Result<Option<VerificationReport>, String> {
+    let mocks = state.mocks.lock().map_err(|err| err.to_string())?;
+    let mock = match mocks.get(&id) {
+        Some(mock) => mock,
+        None => return Ok(None),
+    };
+
+    let expected_at_least = mock.definition.expected_at_least;
+    let expected_at_most = mock.definition.expected_at_most;
+    let satisfied =
+        expected_at_least.is_none_or(|min| mock.hits >= min) && expected_at_most.is_none_or(|max| mock.hits <= max);
+
+    Ok(Some(VerificationReport {
+        hits: mock.hits,
+        expected_at_least,
+        expected_at_most,
+        satisfied,
+    }))
+}
+
+pub(crate) fn request_satisfies(req: &HttpMockRequest, requirements: &RequestRequirements) -> bool {
+    if let Some(path) = &requirements.path {
+        if &req.path != path {
+            return false;
+        }
+    }
+    if let Some(method) = &requirements.method {
+        if &req.method != method {
+            return false;
+        }
+    }
+    if let Some(body) = &requirements.body {
+        if req.body.as_deref() != Some(body.as_str()) {
+            return false;
+        }
+    }
+    if let Some(headers) = &requirements.headers {
+        if !headers.iter().all(|(key, value)| header_matches(&req.headers, key, value)) {
+            return false;
+        }
+    }
+    if let Some(query_params) = &requirements.query_params {
+        if !query_params.iter().all(|(key, value)| query_param_matches(&req.query_params, key, value)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Header names are case-insensitive per RFC 7230; values aren't.
+fn header_matches(headers: &Option<Vec<(String, String)>>, key: &str, value: &str) -> bool {
+    headers
+        .as_ref()
+        .is_some_and(|headers| headers.iter().any(|(k, v)| k.eq_ignore_ascii_case(key) && v == value))
+}
+
+fn query_param_matches(query_params: &Option<Vec<(String, String)>>, key: &str, value: &str) -> bool {
+    query_params.as_ref().is_some_and(|params| params.iter().any(|(k, v)| k == key && v == value))
+}
+
+/// Builds one [Mismatch] per requirement field that the request fails to satisfy, so callers can
+/// render a human-readable reason an incoming request didn't match a given mock.
+fn mismatches_for(req: &HttpMockRequest, requirements: &RequestRequirements) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+
+    if let Some(path) = &requirements.path {
+        if &req.path != path {
+            mismatches.push(Mismatch {
+                title: "path".to_string(),
+                message: None,
+                reason: Some(SimpleDiffResult {
+                    expected: path.clone(),
+                    actual: req.path.clone(),
+                    operation_name: "equals".to_string(),
+                    best_match: false,
+                }),
+                detailed_diff: Some(diff_str(path, &req.path, Tokenizer::Character)),
+                json_diff: None,
+                score: distance_for(path, &req.path),
+            });
+        }
+    }
+
+    if let Some(method) = &requirements.method {
+        if &req.method != method {
+            mismatches.push(Mismatch {
+                title: "method".to_string(),
+                message: None,
+                reason: Some(SimpleDiffResult {
+                    expected: method.clone(),
+                    actual: req.method.clone(),
+                    operation_name: "equals".to_string(),
+                    best_match: false,
+                }),
+                detailed_diff: None,
+                json_diff: None,
+                score: distance_for(method, &req.method),
+            });
+        }
+    }
+
+    if let Some(body) = &requirements.body {
+        let actual = req.body.clone().unwrap_or_default();
+        if body != &actual {
+            mismatches.push(Mismatch {
+                title: "body".to_string(),
+                message: None,
+                reason: Some(SimpleDiffResult {
+                    expected: body.clone(),
+                    actual: actual.clone(),
+                    operation_name: "equals".to_string(),
+                    best_match: false,
+                }),
+                detailed_diff: Some(diff_str(body, &actual, Tokenizer::Line)),
+                json_diff: None,
+                score: distance_for(body, &actual),
+            });
+        }
+    }
+
+    if let Some(headers) = &requirements.headers {
+        for (key, value) in headers {
+            if !header_matches(&req.headers, key, value) {
+                mismatches.push(field_mismatch(&format!("header:{}", key), value, &actual_header(&req.headers, key)));
+            }
+        }
+    }
+
+    if let Some(query_params) = &requirements.query_params {
+        for (key, value) in query_params {
+            if !query_param_matches(&req.query_params, key, value) {
+                mismatches.push(field_mismatch(
+                    &format!("query:{}", key),
+                    value,
+                    &actual_query_param(&req.query_params, key),
+                ));
+            }
+        }
+    }
+
+    mismatches
+}
+
+/// Builds a [Mismatch] for a single expected/actual string pair, scored with [distance_for] like
+/// the built-in comparisons above and diffed character by character like `path`.
+fn field_mismatch(title: &str, expected: &str, actual: &str) -> Mismatch {
+    Mismatch {
+        title: title.to_string(),
+        message: None,
+        reason: Some(SimpleDiffResult {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+            operation_name: "equals".to_string(),
+            best_match: false,
+        }),
+        detailed_diff: Some(diff_str(expected, actual, Tokenizer::Character)),
+        json_diff: None,
+        score: distance_for(expected, actual),
+    }
+}
+
+fn actual_header(headers: &Option<Vec<(String, String)>>, key: &str) -> String {
+    headers
+        .as_ref()
+        .and_then(|headers| headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)))
+        .map_or(String::new(), |(_, v)| v.clone())
+}
+
+fn actual_query_param(query_params: &Option<Vec<(String, String)>>, key: &str) -> String {
+    query_params
+        .as_ref()
+        .and_then(|params| params.iter().find(|(k, _)| k == key))
+        .map_or(String::new(), |(_, v)| v.clone())
+}
+
+/// Finds the registered mock with the lowest aggregate mismatch score against `req`, for
+/// rendering a "closest match" diagnostic when a request matched nothing. Returns `None` when no
+/// mocks are registered at all.
+pub(crate) fn find_closest_match(
+    state: &MockServerState,
+    req: &HttpMockRequest,
+) -> Result<Option<ClosestMatchReport>, String> {
+    let mocks = state.mocks.lock().map_err(|err| err.to_string())?;
+    let custom_matchers = state.custom_matchers.lock().map_err(|err| err.to_string())?;
+
+    let closest = mocks
+        .values()
+        .map(|mock| {
+            let mut mismatches = mismatches_for(req, &mock.definition.request);
+            if let Some(matchers) = custom_matchers.get(&mock.id) {
+                for matcher in matchers {
+                    mismatches.extend(matcher.mismatches(req, &mock.definition.request));
+                }
+            }
+            // Rank the mismatches by score, largest first; ties keep their check order.
+            mismatches.sort_by(|a, b| b.score.cmp(&a.score));
+            let score: usize = mismatches.iter().map(|mismatch| mismatch.score).sum();
+            (mock.id, mismatches, score)
+        })
+        .min_by_key(|(_, _, score)| *score);
+
+    let (mock_id, mismatches, _) = match closest {
+        Some(closest) => closest,
+        None => return Ok(None),
+    };
+
+    let diff = mismatches.iter().map(render_mismatch).collect::<Vec<_>>().join("\n");
+
+    Ok(Some(ClosestMatchReport { mock_id, mismatches, diff }))
+}
+
+/// Renders a single [Mismatch] as one human-readable line, falling back to its `message` (e.g.
+/// for a custom matcher, which has no `reason`) when there's no expected/actual pair to diff.
+fn render_mismatch(mismatch: &Mismatch) -> String {
+    match &mismatch.reason {
+        Some(reason) => {
+            format!("{}: expected '{}', got '{}'", mismatch.title, reason.expected, reason.actual)
+        }
+        None => match &mismatch.message {
+            Some(message) => format!("{}: {}", mismatch.title, message),
+            None => mismatch.title.clone(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::data::MockServerHttpResponse;
+    use crate::server::matchers::ClosureMatcher;
+
+    fn mock_definition(expected_at_least: Option<usize>, expected_at_most: Option<usize>) -> MockDefinition {
+        MockDefinition {
+            request: RequestRequirements { path: Some("/hello".to_string()), ..Default::default() },
+            response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+            expected_at_least,
+            expected_at_most,
+        }
+    }
+
+    fn hello_request() -> HttpMockRequest {
+        HttpMockRequest {
+            path: "/hello".to_string(),
+            method: "GET".to_string(),
+            headers: None,
+            query_params: None,
+            body: None,
+        }
+    }
+
+    #[test]
+    fn verify_one_reports_unsatisfied_below_minimum() {
+        let state = MockServerState::new();
+        let id = add_new_mock(&state, mock_definition(Some(2), None)).unwrap();
+
+        find_match(&state, &hello_request()).unwrap();
+
+        let report = verify_one(&state, id).unwrap().unwrap();
+        assert_eq!(report.hits, 1);
+        assert!(!report.satisfied);
+    }
+
+    #[test]
+    fn verify_one_reports_satisfied_within_bounds() {
+        let state = MockServerState::new();
+        let id = add_new_mock(&state, mock_definition(Some(1), Some(2))).unwrap();
+
+        find_match(&state, &hello_request()).unwrap();
+        find_match(&state, &hello_request()).unwrap();
+
+        let report = verify_one(&state, id).unwrap().unwrap();
+        assert_eq!(report.hits, 2);
+        assert!(report.satisfied);
+    }
+
+    #[test]
+    fn verify_one_returns_none_for_unknown_mock() {
+        let state = MockServerState::new();
+        assert_eq!(None, verify_one(&state, 42).unwrap());
+    }
+
+    #[test]
+    fn find_match_requires_header_and_query_param_to_be_present() {
+        let state = MockServerState::new();
+        let id = add_new_mock(
+            &state,
+            MockDefinition {
+                request: RequestRequirements {
+                    path: Some("/hello".to_string()),
+                    headers: Some(vec![("x-api-key".to_string(), "secret".to_string())]),
+                    query_params: Some(vec![("page".to_string(), "1".to_string())]),
+                    ..Default::default()
+                },
+                response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+                expected_at_least: None,
+                expected_at_most: None,
+            },
+        )
+        .unwrap();
+
+        let missing_header = HttpMockRequest {
+            path: "/hello".to_string(),
+            method: "GET".to_string(),
+            headers: None,
+            query_params: Some(vec![("page".to_string(), "1".to_string())]),
+            body: None,
+        };
+        assert_eq!(None, find_match(&state, &missing_header).unwrap());
+
+        // Header names are matched case-insensitively, query params are not.
+        let satisfied = HttpMockRequest {
+            path: "/hello".to_string(),
+            method: "GET".to_string(),
+            headers: Some(vec![("X-API-Key".to_string(), "secret".to_string())]),
+            query_params: Some(vec![("page".to_string(), "1".to_string())]),
+            body: None,
+        };
+        assert_eq!(Some(id), find_match(&state, &satisfied).unwrap());
+    }
+
+    #[test]
+    fn find_closest_match_picks_lowest_score_mock() {
+        let state = MockServerState::new();
+        add_new_mock(&state, mock_definition(None, None)).unwrap();
+        let closer_id = add_new_mock(
+            &state,
+            MockDefinition {
+                request: RequestRequirements { path: Some("/hell".to_string()), ..Default::default() },
+                response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+                expected_at_least: None,
+                expected_at_most: None,
+            },
+        )
+        .unwrap();
+
+        let report = find_closest_match(
+            &state,
+            &HttpMockRequest {
+                path: "/hell".to_string(),
+                method: "GET".to_string(),
+                headers: None,
+                query_params: None,
+                body: None,
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(closer_id, report.mock_id);
+    }
+
+    #[test]
+    fn find_closest_match_returns_none_when_no_mocks_registered() {
+        let state = MockServerState::new();
+        assert!(find_closest_match(&state, &hello_request()).unwrap().is_none());
+    }
+
+    #[test]
+    fn find_closest_match_prefers_genuinely_closer_mock_when_neither_matches_exactly() {
+        let state = MockServerState::new();
+        add_new_mock(
+            &state,
+            MockDefinition {
+                request: RequestRequirements { path: Some("/zzzzzzz".to_string()), ..Default::default() },
+                response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+                expected_at_least: None,
+                expected_at_most: None,
+            },
+        )
+        .unwrap();
+        let closer_id = add_new_mock(
+            &state,
+            MockDefinition {
+                request: RequestRequirements { path: Some("/helln".to_string()), ..Default::default() },
+                response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+                expected_at_least: None,
+                expected_at_most: None,
+            },
+        )
+        .unwrap();
+
+        let report = find_closest_match(&state, &hello_request()).unwrap().unwrap();
+
+        assert_eq!(closer_id, report.mock_id);
+    }
+
+    #[test]
+    fn find_closest_match_reports_a_header_only_mismatch() {
+        let state = MockServerState::new();
+        add_new_mock(
+            &state,
+            MockDefinition {
+                request: RequestRequirements {
+                    headers: Some(vec![("x-api-key".to_string(), "secret".to_string())]),
+                    ..Default::default()
+                },
+                response: MockServerHttpResponse { status: Some(200), headers: None, body: None },
+                expected_at_least: None,
+                expected_at_most: None,
+            },
+        )
+        .unwrap();
+
+        let report = find_closest_match(
+            &state,
+            &HttpMockRequest {
+                path: "/".to_string(),
+                method: "GET".to_string(),
+                headers: Some(vec![("x-api-key".to_string(), "wrong".to_string())]),
+                query_params: None,
+                body: None,
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(report.diff.contains("header:x-api-key"));
+    }
+
+    #[test]
+    fn find_closest_match_accounts_for_custom_matcher_rejection() {
+        let state = MockServerState::new();
+        let id = add_new_mock(&state, mock_definition(None, None)).unwrap();
+        state.custom_matchers.lock().unwrap().insert(
+            id,
+            vec![ClosureMatcher { matcher: Box::new(|_| false), mismatch_description: None }],
+        );
+
+        let report = find_closest_match(&state, &hello_request()).unwrap().unwrap();
+
+        assert!(!report.mismatches.is_empty());
+    }
+}
@@ -10,6 +10,7 @@ mod util;
 
 use basic_cookies::Cookie;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::BTreeMap;
 use std::fmt::Display;
 
@@ -75,6 +76,9 @@ pub(crate) struct Mismatch {
     pub message: Option<String>,
     pub reason: Option<SimpleDiffResult>,
     pub detailed_diff: Option<DetailedDiffResult>,
+    /// Structured, JSON-pointer-keyed mismatches, populated by [JsonBodyMatcher]; `None` for
+    /// comparisons that have nothing JSON-shaped to report.
+    pub json_diff: Option<Vec<JsonMismatch>>,
     pub score: usize,
 }
 
@@ -83,6 +87,212 @@ pub(crate) trait Matcher {
     fn mismatches(&self, req: &HttpMockRequest, mock: &RequestRequirements) -> Vec<Mismatch>;
 }
 
+/// Describes why a [ClosureMatcher] rejected a request, for inclusion in a [Mismatch].
+pub(crate) type MismatchDescription = Box<dyn Fn(&HttpMockRequest) -> String + Send + Sync>;
+
+/// A [Matcher] backed by a user-supplied predicate closure, for requests that the built-in
+/// comparators can't express (e.g. a signature header or a body checksum). The mock builder's
+/// `match_custom` registers these on `MockServerState`; since closures can't be serialized,
+/// custom matchers only work against a `LocalMockServerAdapter`, never a remote one.
+pub(crate) struct ClosureMatcher {
+    pub matcher: Box<dyn Fn(&HttpMockRequest) -> bool + Send + Sync>,
+    pub mismatch_description: Option<MismatchDescription>,
+}
+
+impl Matcher for ClosureMatcher {
+    fn matches(&self, req: &HttpMockRequest, _mock: &RequestRequirements) -> bool {
+        (self.matcher)(req)
+    }
+
+    fn mismatches(&self, req: &HttpMockRequest, _mock: &RequestRequirements) -> Vec<Mismatch> {
+        if (self.matcher)(req) {
+            return Vec::new();
+        }
+
+        vec![Mismatch {
+            title: "Custom matcher did not match".to_string(),
+            message: self.mismatch_description.as_ref().map(|describe| describe(req)),
+            reason: None,
+            detailed_diff: None,
+            json_diff: None,
+            score: 100,
+        }]
+    }
+}
+
+// *************************************************************************************************
+// Structural JSON comparison. Unlike diff_str, this walks expected/actual in lockstep so that key
+// order and whitespace don't matter, and reports mismatches by JSON pointer path instead of a
+// character changeset.
+// *************************************************************************************************
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Copy)]
+pub(crate) enum JsonMatchMode {
+    /// Expected and actual must be equal, recursively (objects compared key-for-key).
+    Exact,
+    /// Expected must be contained in actual: every key/value in expected must be present in
+    /// actual, but actual may carry additional keys.
+    Subset,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+pub(crate) enum JsonMismatchKind {
+    ValueMismatch,
+    MissingKey,
+    ExtraKey,
+    LengthMismatch,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct JsonMismatch {
+    pub pointer: String,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+    pub kind: JsonMismatchKind,
+}
+
+pub(crate) fn diff_json(expected: &Value, actual: &Value, mode: JsonMatchMode) -> Vec<JsonMismatch> {
+    let mut mismatches = Vec::new();
+    diff_json_at("", expected, actual, mode, &mut mismatches);
+    mismatches
+}
+
+fn diff_json_at(
+    pointer: &str,
+    expected: &Value,
+    actual: &Value,
+    mode: JsonMatchMode,
+    out: &mut Vec<JsonMismatch>,
+) {
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            for (key, expected_value) in expected_map {
+                let child_pointer = format!("{}/{}", pointer, key);
+                match actual_map.get(key) {
+                    Some(actual_value) => {
+                        diff_json_at(&child_pointer, expected_value, actual_value, mode, out)
+                    }
+                    None => out.push(JsonMismatch {
+                        pointer: child_pointer,
+                        expected: Some(expected_value.clone()),
+                        actual: None,
+                        kind: JsonMismatchKind::MissingKey,
+                    }),
+                }
+            }
+            if mode == JsonMatchMode::Exact {
+                for (key, actual_value) in actual_map {
+                    if !expected_map.contains_key(key) {
+                        out.push(JsonMismatch {
+                            pointer: format!("{}/{}", pointer, key),
+                            expected: None,
+                            actual: Some(actual_value.clone()),
+                            kind: JsonMismatchKind::ExtraKey,
+                        });
+                    }
+                }
+            }
+        }
+        (Value::Array(expected_vec), Value::Array(actual_vec)) => {
+            if mode == JsonMatchMode::Exact && expected_vec.len() != actual_vec.len() {
+                out.push(JsonMismatch {
+                    pointer: pointer.to_string(),
+                    expected: Some(expected.clone()),
+                    actual: Some(actual.clone()),
+                    kind: JsonMismatchKind::LengthMismatch,
+                });
+            }
+            for (idx, expected_item) in expected_vec.iter().enumerate() {
+                let child_pointer = format!("{}/{}", pointer, idx);
+                match actual_vec.get(idx) {
+                    Some(actual_item) => {
+                        diff_json_at(&child_pointer, expected_item, actual_item, mode, out)
+                    }
+                    None => out.push(JsonMismatch {
+                        pointer: child_pointer,
+                        expected: Some(expected_item.clone()),
+                        actual: None,
+                        kind: JsonMismatchKind::MissingKey,
+                    }),
+                }
+            }
+        }
+        _ => {
+            if expected != actual {
+                out.push(JsonMismatch {
+                    pointer: pointer.to_string(),
+                    expected: Some(expected.clone()),
+                    actual: Some(actual.clone()),
+                    kind: JsonMismatchKind::ValueMismatch,
+                });
+            }
+        }
+    }
+}
+
+/// A [Matcher] that compares a request body as JSON rather than byte-for-byte, so key order and
+/// whitespace don't cause spurious mismatches. In [JsonMatchMode::Subset] mode `expected` only
+/// needs to be contained in the request body; [JsonMatchMode::Exact] requires full equality.
+pub(crate) struct JsonBodyMatcher {
+    pub expected: Value,
+    pub mode: JsonMatchMode,
+}
+
+impl JsonBodyMatcher {
+    fn diff(&self, req: &HttpMockRequest) -> Vec<JsonMismatch> {
+        let actual = match req.body.as_deref().and_then(|body| serde_json::from_str(body).ok()) {
+            Some(actual) => actual,
+            None => {
+                return vec![JsonMismatch {
+                    pointer: "".to_string(),
+                    expected: Some(self.expected.clone()),
+                    actual: None,
+                    kind: JsonMismatchKind::ValueMismatch,
+                }]
+            }
+        };
+
+        diff_json(&self.expected, &actual, self.mode)
+    }
+}
+
+impl Matcher for JsonBodyMatcher {
+    fn matches(&self, req: &HttpMockRequest, _mock: &RequestRequirements) -> bool {
+        self.diff(req).is_empty()
+    }
+
+    fn mismatches(&self, req: &HttpMockRequest, _mock: &RequestRequirements) -> Vec<Mismatch> {
+        let diffs = self.diff(req);
+        if diffs.is_empty() {
+            return Vec::new();
+        }
+
+        let message = diffs
+            .iter()
+            .map(|diff| match diff.kind {
+                JsonMismatchKind::MissingKey => format!("missing key {}", diff.pointer),
+                JsonMismatchKind::ExtraKey => format!("unexpected key {}", diff.pointer),
+                JsonMismatchKind::LengthMismatch => format!("array length mismatch at {}", diff.pointer),
+                JsonMismatchKind::ValueMismatch => format!(
+                    "{} expected {} got {}",
+                    diff.pointer,
+                    diff.expected.as_ref().map_or("<none>".to_string(), Value::to_string),
+                    diff.actual.as_ref().map_or("<none>".to_string(), Value::to_string),
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        vec![Mismatch {
+            title: "JSON body comparison".to_string(),
+            message: Some(message),
+            reason: None,
+            detailed_diff: None,
+            score: diffs.len(),
+            json_diff: Some(diffs),
+        }]
+    }
+}
+
 // *************************************************************************************************
 // Helper functions
 // *************************************************************************************************
@@ -107,12 +317,12 @@ pub(crate) fn parse_cookies(req: &HttpMockRequest) -> Result<BTreeMap<String, St
 }
 
 pub(crate) fn distance_for(expected: &str, actual: &str) -> usize {
-    let max_distance = (expected.len() + actual.len());
+    let max_distance = expected.len() + actual.len();
     if max_distance == 0 {
         return 0;
     }
     let distance = levenshtein::levenshtein(expected, actual);
-    100 - ((max_distance - distance) / max_distance)
+    100 - ((max_distance - distance) * 100 / max_distance)
 }
 
 pub(crate) fn distance_for_vec(expected: &str, actual: &Vec<String>) -> usize {
@@ -132,3 +342,94 @@ where
 fn diff_str_new(s1: &str, s2: &str) -> usize {
     levenshtein::levenshtein(s1, s2)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request_with_body(body: &str) -> HttpMockRequest {
+        HttpMockRequest {
+            path: "/".to_string(),
+            method: "POST".to_string(),
+            headers: None,
+            query_params: None,
+            body: Some(body.to_string()),
+        }
+    }
+
+    #[test]
+    fn diff_json_exact_detects_value_and_missing_key_mismatches() {
+        let expected = json!({"user": {"name": "bob", "roles": ["admin"]}});
+        let actual = json!({"user": {"name": "alice"}});
+
+        let diffs = diff_json(&expected, &actual, JsonMatchMode::Exact);
+
+        assert_eq!(2, diffs.len());
+        assert!(diffs.iter().any(|d| d.pointer == "/user/name" && d.kind == JsonMismatchKind::ValueMismatch));
+        assert!(diffs.iter().any(|d| d.pointer == "/user/roles" && d.kind == JsonMismatchKind::MissingKey));
+    }
+
+    #[test]
+    fn diff_json_subset_ignores_extra_actual_keys() {
+        let expected = json!({"name": "bob"});
+        let actual = json!({"name": "bob", "age": 42});
+
+        assert!(diff_json(&expected, &actual, JsonMatchMode::Subset).is_empty());
+        assert_eq!(1, diff_json(&expected, &actual, JsonMatchMode::Exact).len());
+    }
+
+    #[test]
+    fn json_body_matcher_matches_regardless_of_key_order() {
+        let matcher = JsonBodyMatcher {
+            expected: json!({"a": 1, "b": 2}),
+            mode: JsonMatchMode::Exact,
+        };
+        let req = request_with_body(r#"{"b": 2, "a": 1}"#);
+        let requirements = RequestRequirements::default();
+
+        assert!(matcher.matches(&req, &requirements));
+        assert!(matcher.mismatches(&req, &requirements).is_empty());
+    }
+
+    #[test]
+    fn json_body_matcher_reports_mismatch() {
+        let matcher = JsonBodyMatcher {
+            expected: json!({"a": 1}),
+            mode: JsonMatchMode::Exact,
+        };
+        let req = request_with_body(r#"{"a": 2}"#);
+        let requirements = RequestRequirements::default();
+
+        assert!(!matcher.matches(&req, &requirements));
+        let mismatches = matcher.mismatches(&req, &requirements);
+        assert_eq!(1, mismatches.len());
+
+        let json_diff = mismatches[0].json_diff.as_ref().expect("structured JSON diff");
+        assert_eq!(1, json_diff.len());
+        assert_eq!("/a", json_diff[0].pointer);
+        assert_eq!(JsonMismatchKind::ValueMismatch, json_diff[0].kind);
+    }
+
+    #[test]
+    fn distance_for_ranks_a_near_miss_closer_than_a_total_mismatch() {
+        let near_miss = distance_for("/hello", "/hell");
+        let total_mismatch = distance_for("/hello", "/xxxxx");
+
+        assert!(near_miss < total_mismatch, "{} should be < {}", near_miss, total_mismatch);
+    }
+
+    #[test]
+    fn closure_matcher_reports_mismatch_message() {
+        let matcher = ClosureMatcher {
+            matcher: Box::new(|req| req.path == "/allowed"),
+            mismatch_description: Some(Box::new(|req| format!("unexpected path {}", req.path))),
+        };
+        let req = request_with_body("");
+        let requirements = RequestRequirements::default();
+
+        let mismatches = matcher.mismatches(&req, &requirements);
+        assert_eq!(1, mismatches.len());
+        assert_eq!(Some("unexpected path /".to_string()), mismatches[0].message);
+    }
+}
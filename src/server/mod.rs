@@ -0,0 +1,3 @@
+pub(crate) mod data;
+pub(crate) mod handlers;
+pub(crate) mod matchers;